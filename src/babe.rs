@@ -46,7 +46,16 @@
 //! The "randomess value" of an epoch `N` is calculated by combining the generated numbers of all
 //! the blocks of the epoch `N - 2`.
 //!
-//! TODO: read about and explain the secondary slot stuff
+//! ## Secondary slots
+//!
+//! Not every slot is guaranteed to have an eligible primary claimant, which would otherwise
+//! leave the chain without a block for that slot. To keep the chain progressing, the epoch's
+//! configuration can additionally allow **secondary slots**: every slot has an accompanying
+//! deterministic "secondary" author, computed from the slot number and the epoch randomness,
+//! who is allowed to produce a block if nobody produces a primary-claimed one. Secondary slot
+//! claims come in two flavours: "plain", which carry no further proof beyond the seal signature,
+//! and "VRF", which additionally embed a VRF output and proof (but, unlike primary claims, this
+//! output isn't compared against a threshold).
 //!
 //! ## Chain selection
 //!
@@ -60,31 +69,60 @@
 //! case the winning block is the one upon which the next block author builds upon.
 //!
 
-use crate::executor;
-use parity_scale_codec::DecodeAll as _;
-
 mod definitions;
 mod runtime;
 
 pub mod chain_config;
+pub mod chain_selection;
+pub mod epoch;
+pub mod equivocation;
+pub mod read;
 
 pub use chain_config::BabeGenesisConfiguration;
+pub use chain_selection::{best_block, compare_chains, ChainScore};
+pub use epoch::{EpochChange, EpochConfigChange, EpochInformation};
+pub use equivocation::{Equivocation, SlotClaimTracker};
+pub use read::{read_header, HeaderInfo, ReadError};
 
 /// Failure to verify a block.
 #[derive(Debug, Clone, derive_more::Display)]
 pub enum VerifyError {
-    /// Header passed is of the wrong format.
-    InvalidHeader,
-    /// The seal (containing the signature of the authority) is missing from the header.
-    MissingSeal,
-    /// No pre-runtime digest in the block header.
-    MissingPreRuntimeDigest,
-    /// There are multiple pre-runtime digests in the block header.
-    MultiplePreRuntimeDigests,
-    /// Failed to decode pre-runtime digest.
-    PreRuntimeDigestDecodeError(parity_scale_codec::Error),
-    /// Failed to decode a consensus digest.
-    ConsensusDigestDecodeError(parity_scale_codec::Error),
+    /// Failed to extract the BABE information from the header.
+    Read(ReadError),
+    /// The `authority_index` found in the pre-runtime digest doesn't correspond to any authority
+    /// in the epoch's authority list.
+    InvalidAuthorityIndex,
+    /// The seal doesn't correspond to a valid sr25519 signature of the claiming authority.
+    BadSignature,
+    /// The VRF output and/or proof found in the pre-runtime digest are invalid.
+    VrfVerificationFailed,
+    /// The VRF output is valid but is over the slot-claiming threshold.
+    OverThreshold,
+    /// The author of a secondary slot claim isn't the expected deterministic slot leader.
+    BadSecondarySlotAuthor,
+    /// The header contains a secondary slot claim, but the epoch's configuration doesn't allow
+    /// secondary slot claims of that kind (plain or VRF).
+    SecondarySlotsDisabled,
+    /// The header contains more than one epoch-change (`NextEpochData`) consensus digest.
+    MultipleEpochChangeDigests,
+}
+
+/// Successful verification of a header produced by [`verify_header`].
+#[derive(Debug, Clone)]
+pub struct VerifySuccess {
+    /// Slot number during which the verified block was authored.
+    pub slot_number: u64,
+
+    /// Index, within the epoch's authority list, of the authority that authored the block.
+    pub authority_index: u32,
+
+    /// If the header announces the parameters of the epoch two epochs ahead, contains these
+    /// parameters.
+    pub epoch_change: Option<EpochChange>,
+
+    /// If a [`SlotClaimTracker`] was passed in [`VerifyConfig::slot_claim_tracker`] and this
+    /// verification revealed that the claiming authority equivocated, contains the proof.
+    pub equivocation: Option<Equivocation>,
 }
 
 /// Configuration for [`verify_header`].
@@ -92,85 +130,292 @@ pub struct VerifyConfig<'a> {
     /// SCALE-encoded header of the block.
     pub scale_encoded_header: &'a [u8],
 
-    /// BABE configuration retrieved from the genesis block.
+    /// Authorities, randomness, and configuration of the epoch the block to verify belongs to.
     ///
-    /// Can be obtained by calling [`BabeGenesisConfiguration::from_virtual_machine_prototype`]
-    /// with the runtime of the genesis block.
-    pub genesis_configuration: &'a BabeGenesisConfiguration,
+    /// For epochs 0 and 1, this can be built with
+    /// [`EpochInformation::from_genesis_configuration`] from the chain's
+    /// [`BabeGenesisConfiguration`], passing the epoch being verified as the epoch index. For
+    /// later epochs, it is obtained by applying, epoch after epoch, the [`EpochChange`] found in
+    /// [`VerifySuccess::epoch_change`] to the previous [`EpochInformation`] with
+    /// [`EpochInformation::apply_epoch_change`].
+    pub epoch_information: &'a EpochInformation,
+
+    /// Optional tracker used to detect BABE equivocations across the headers verified with it.
+    /// If `None`, no equivocation detection is performed.
+    pub slot_claim_tracker: Option<&'a mut SlotClaimTracker>,
 }
 
 /// Verifies whether a block header provides a correct proof of the legitimacy of the authorship.
-pub fn verify_header(config: VerifyConfig) -> Result<(), VerifyError> {
-    let header = crate::block::Header::decode_all(config.scale_encoded_header)
-        .map_err(|_| VerifyError::InvalidHeader)?;
-
-    // TODO: idea: move the information extraction to a separate module, to split the extraction
-    // from verification; this way, users can simply examine a header
-
-    // Part of the rules is that the last digest log of the header must always be the seal,
-    // containing a signature of the rest of the header and made by the author of the block.
-    let seal_signature: &Vec<u8> = header
-        .digest
-        .logs
-        .last()
-        .and_then(|l| match l {
-            crate::block::DigestItem::Seal(engine, signature) if engine == b"BABE" => {
-                Some(signature)
+pub fn verify_header(mut config: VerifyConfig) -> Result<VerifySuccess, VerifyError> {
+    let HeaderInfo {
+        seal_signature,
+        pre_digest: pre_runtime,
+        consensus_logs,
+        pre_seal_hash,
+    } = read_header(config.scale_encoded_header).map_err(VerifyError::Read)?;
+
+    // The header can contain consensus digest logs, indicating an epoch transition or a
+    // configuration change. At most one `NextEpochData` is allowed per header.
+    let epoch_change = {
+        let mut next_epoch_data = None;
+        let mut next_config_data = None;
+
+        for log in &consensus_logs {
+            match log {
+                definitions::ConsensusLog::NextEpochData(descriptor) => {
+                    if next_epoch_data.is_some() {
+                        return Err(VerifyError::MultipleEpochChangeDigests);
+                    }
+                    next_epoch_data = Some(descriptor);
+                }
+                definitions::ConsensusLog::NextConfigData(
+                    definitions::NextConfigDescriptor::V1 { c, allowed_slots },
+                ) => {
+                    next_config_data = Some(EpochConfigChange {
+                        c: *c,
+                        allowed_slots: *allowed_slots,
+                    });
+                }
+                definitions::ConsensusLog::OnDisabled(_) => {}
             }
-            _ => None,
-        })
-        .ok_or(VerifyError::MissingSeal)?;
-
-    // Additionally, one of the digest logs of the header must be a BABE pre-runtime digest whose
-    // content contains the slot claim made by the author.
-    let pre_runtime: definitions::PreDigest = {
-        let mut pre_runtime_digests = header.digest.logs.iter().filter_map(|l| match l {
-            crate::block::DigestItem::PreRuntime(engine, data) if engine == b"BABE" => Some(data),
-            _ => None,
-        });
-        let pre_runtime = pre_runtime_digests
-            .next()
-            .ok_or(VerifyError::MissingPreRuntimeDigest)?;
-        if pre_runtime_digests.next().is_some() {
-            return Err(VerifyError::MultiplePreRuntimeDigests);
         }
-        definitions::PreDigest::decode_all(&pre_runtime)
-            .map_err(VerifyError::PreRuntimeDigestDecodeError)?
+
+        next_epoch_data.map(|descriptor| EpochChange {
+            authorities: descriptor.authorities.clone(),
+            randomness: descriptor.randomness,
+            config: next_config_data,
+        })
     };
 
-    // Finally, the header can contain consensus digest logs, indicating an epoch transition or
-    // a configuration change.
-    let consensus_logs: Vec<definitions::ConsensusLog> = {
-        let list = header.digest.logs.iter().filter_map(|l| match l {
-            crate::block::DigestItem::Consensus(engine, data) if engine == b"BABE" => Some(data),
-            _ => None,
-        });
-
-        let mut consensus_logs = Vec::with_capacity(header.digest.logs.len());
-        for digest in list {
-            let decoded = definitions::ConsensusLog::decode_all(&digest)
-                .map_err(VerifyError::ConsensusDigestDecodeError)?;
-            consensus_logs.push(decoded)
+    // Look up the authority that claims to have authored this block in the epoch's authority
+    // list.
+    let (authority_public_key, authority_weight) = config
+        .epoch_information
+        .authorities
+        .get(usize::try_from(pre_runtime.authority_index()).unwrap_or(usize::MAX))
+        .ok_or(VerifyError::InvalidAuthorityIndex)?;
+
+    // Verify the seal, which is the signature of `pre_seal_hash` by the claiming authority.
+    let seal_signature = crate::sign::sr25519::Signature::try_from(&seal_signature[..])
+        .map_err(|_| VerifyError::BadSignature)?;
+    if !authority_public_key.verify(&pre_seal_hash, &seal_signature) {
+        return Err(VerifyError::BadSignature);
+    }
+
+    match &pre_runtime {
+        definitions::PreDigest::Primary(digest) => {
+            let (vrf_in_out, _) = verify_vrf(
+                &config,
+                authority_public_key,
+                digest.slot_number,
+                &digest.vrf_output,
+                &digest.vrf_proof,
+            )?;
+
+            let total_weight = config
+                .epoch_information
+                .authorities
+                .iter()
+                .map(|(_, weight)| *weight)
+                .sum::<u64>();
+            let threshold = calculate_primary_threshold(
+                config.epoch_information.c,
+                *authority_weight,
+                total_weight,
+            );
+            let vrf_output_value = u128::from_le_bytes(vrf_in_out.make_bytes(b"substrate-babe-vrf"));
+            if vrf_output_value >= threshold {
+                return Err(VerifyError::OverThreshold);
+            }
+        }
+        definitions::PreDigest::SecondaryPlain(digest) => {
+            if !config.epoch_information.allowed_slots.is_secondary_plain_allowed() {
+                return Err(VerifyError::SecondarySlotsDisabled);
+            }
+
+            let expected_authority_index = secondary_slot_author_index(
+                digest.slot_number,
+                &config.epoch_information.randomness,
+                config.epoch_information.authorities.len(),
+            )
+            .ok_or(VerifyError::BadSecondarySlotAuthor)?;
+            if digest.authority_index != expected_authority_index {
+                return Err(VerifyError::BadSecondarySlotAuthor);
+            }
         }
-        consensus_logs
+        definitions::PreDigest::SecondaryVRF(digest) => {
+            if !config.epoch_information.allowed_slots.is_secondary_vrf_allowed() {
+                return Err(VerifyError::SecondarySlotsDisabled);
+            }
+
+            let expected_authority_index = secondary_slot_author_index(
+                digest.slot_number,
+                &config.epoch_information.randomness,
+                config.epoch_information.authorities.len(),
+            )
+            .ok_or(VerifyError::BadSecondarySlotAuthor)?;
+            if digest.authority_index != expected_authority_index {
+                return Err(VerifyError::BadSecondarySlotAuthor);
+            }
+
+            // The VRF output and proof must be valid, but (unlike primary claims) the output is
+            // not compared against any threshold.
+            verify_vrf(
+                &config,
+                authority_public_key,
+                digest.slot_number,
+                &digest.vrf_output,
+                &digest.vrf_proof,
+            )?;
+        }
+    }
+
+    let equivocation = config.slot_claim_tracker.as_mut().and_then(|tracker| {
+        tracker.observe(
+            pre_runtime.slot_number(),
+            pre_runtime.authority_index(),
+            authority_public_key.clone(),
+            pre_seal_hash,
+        )
+    });
+
+    Ok(VerifySuccess {
+        slot_number: pre_runtime.slot_number(),
+        authority_index: pre_runtime.authority_index(),
+        epoch_change,
+        equivocation,
+    })
+}
+
+/// Verifies the VRF output and proof embedded in a primary or secondary-VRF slot claim.
+fn verify_vrf(
+    config: &VerifyConfig,
+    authority_public_key: &crate::sign::sr25519::PublicKey,
+    slot_number: u64,
+    vrf_output: &[u8; 32],
+    vrf_proof: &[u8; 64],
+) -> Result<(schnorrkel::vrf::VRFInOut, schnorrkel::vrf::VRFProofBatchable), VerifyError> {
+    let transcript = {
+        let mut transcript = merlin::Transcript::new(b"BABE");
+        transcript.append_u64(b"slot number", slot_number);
+        transcript.append_u64(b"current epoch", config.epoch_information.epoch_index);
+        transcript.append_message(b"chain randomness", &config.epoch_information.randomness);
+        transcript
     };
 
-    // The signature of the block header applies to the header from where the signature isn't
-    // present.
-    let pre_seal_hash = {
-        let mut unsealed_header = header.clone();
-        let _popped = unsealed_header.digest.logs.pop();
-        debug_assert!(matches!(
-            _popped,
-            Some(crate::block::DigestItem::Seal(_, _))
-        ));
-        unsealed_header.block_hash()
+    let public_key = schnorrkel::PublicKey::from_bytes(authority_public_key.as_bytes())
+        .map_err(|_| VerifyError::BadSignature)?;
+    let vrf_output = schnorrkel::vrf::VRFOutput::from_bytes(vrf_output)
+        .map_err(|_| VerifyError::VrfVerificationFailed)?;
+    let vrf_proof = schnorrkel::vrf::VRFProof::from_bytes(vrf_proof)
+        .map_err(|_| VerifyError::VrfVerificationFailed)?;
+    public_key
+        .vrf_verify(transcript, &vrf_output, &vrf_proof)
+        .map_err(|_| VerifyError::VrfVerificationFailed)
+}
+
+/// Computes the index, within the authority list, of the deterministic secondary slot leader for
+/// the given slot, or `None` if `num_authorities` is zero.
+fn secondary_slot_author_index(
+    slot_number: u64,
+    randomness: &[u8; 32],
+    num_authorities: usize,
+) -> Option<u32> {
+    if num_authorities == 0 {
+        return None;
+    }
+
+    let mut input = randomness.to_vec();
+    input.extend_from_slice(&slot_number.to_le_bytes());
+    let hash = crate::hash::blake2_256(&input);
+    let index =
+        primitive_types::U256::from_big_endian(&hash) % primitive_types::U256::from(num_authorities);
+    Some(index.low_u32())
+}
+
+/// Calculates the threshold, expressed as a `u128`, below which a primary slot claim's VRF
+/// output must fall in order to be considered valid.
+///
+/// The formula, as used by Substrate, is: `2^128 * (1 - (1 - c) ^ (authority_weight /
+/// total_weight))`, where `c` is the configured constant controlling the density of primary
+/// blocks.
+fn calculate_primary_threshold(c: (u64, u64), authority_weight: u64, total_weight: u64) -> u128 {
+    if c.1 == 0 || total_weight == 0 {
+        return 0;
+    }
+
+    let c = c.0 as f64 / c.1 as f64;
+    let p = authority_weight as f64 / total_weight as f64;
+    let threshold = 1f64 - (1f64 - c).powf(p);
+
+    if threshold <= 0.0 {
+        return 0;
+    }
+    if threshold >= 1.0 {
+        return u128::MAX;
+    }
+
+    // `threshold`'s IEEE 754 bit pattern encodes the exact rational number it represents, as
+    // `mantissa * 2^exponent`. Computing `2^128 * threshold` as that exact power-of-two shift,
+    // rather than multiplying by `2^128` as an `f64` and truncating the product to a `u128`,
+    // avoids throwing away the ~75 low bits of the result that don't fit in an `f64` mantissa.
+    // This mirrors Substrate's use of exact rational arithmetic for the same computation.
+    let bits = threshold.to_bits();
+    let biased_exponent = (bits >> 52) & 0x7ff;
+    let (mantissa, exponent) = if biased_exponent == 0 {
+        // Subnormal: no implicit leading bit.
+        ((bits & 0xf_ffff_ffff_ffff) as u128, -1074i64)
+    } else {
+        (
+            ((bits & 0xf_ffff_ffff_ffff) | 0x10_0000_0000_0000) as u128,
+            biased_exponent as i64 - 1075,
+        )
     };
+    let shift = 128 + exponent;
+
+    if shift <= 0 {
+        mantissa.checked_shr((-shift) as u32).unwrap_or(0)
+    } else {
+        mantissa << (shift as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_is_zero_for_zero_c() {
+        assert_eq!(calculate_primary_threshold((0, 1), 1, 1), 0);
+    }
 
-    if !consensus_logs.is_empty() {
-        println!("logs: {:?}", consensus_logs);
+    #[test]
+    fn threshold_is_max_for_full_share_and_c_of_one() {
+        assert_eq!(calculate_primary_threshold((1, 1), 1, 1), u128::MAX);
     }
 
-    // TODO:
-    Ok(())
+    #[test]
+    fn threshold_increases_with_authority_weight() {
+        let low = calculate_primary_threshold((1, 4), 1, 10);
+        let high = calculate_primary_threshold((1, 4), 5, 10);
+        assert!(high > low);
+    }
+
+    #[test]
+    fn threshold_is_zero_when_total_weight_is_zero() {
+        assert_eq!(calculate_primary_threshold((1, 4), 0, 0), 0);
+    }
+
+    #[test]
+    fn secondary_slot_author_index_is_none_for_empty_authority_set() {
+        assert_eq!(secondary_slot_author_index(1, &[0; 32], 0), None);
+    }
+
+    #[test]
+    fn secondary_slot_author_index_is_deterministic_and_in_range() {
+        let a = secondary_slot_author_index(42, &[7; 32], 5);
+        let b = secondary_slot_author_index(42, &[7; 32], 5);
+        assert_eq!(a, b);
+        assert!(a.unwrap() < 5);
+    }
 }
@@ -0,0 +1,80 @@
+//! BABE configuration of a chain, as extracted from the genesis block.
+
+use crate::executor;
+
+/// Chain configuration read from the BABE runtime API, containing everything that is necessary
+/// to verify the blocks production of a chain.
+///
+/// See [`BabeGenesisConfiguration::from_virtual_machine_prototype`].
+#[derive(Debug, Clone)]
+pub struct BabeGenesisConfiguration {
+    /// Number of slots contained in one epoch.
+    pub epoch_length: u64,
+
+    /// Duration, in milliseconds, of a slot.
+    pub slot_duration: u64,
+
+    /// Value of the constant `c` (expressed as a rational number) used to calculate the
+    /// slot-claiming threshold of primary slots.
+    pub c: (u64, u64),
+
+    /// Types of slots that authorities are allowed to claim during the first two epochs.
+    pub allowed_slots: AllowedSlots,
+
+    /// List of authorities, alongside with their weight, that are allowed to author blocks
+    /// during the first two epochs.
+    pub genesis_authorities: Vec<(crate::sign::sr25519::PublicKey, u64)>,
+
+    /// Randomness value for the first two epochs.
+    pub randomness: [u8; 32],
+}
+
+/// See [`BabeGenesisConfiguration::allowed_slots`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, parity_scale_codec::Encode, parity_scale_codec::Decode)]
+pub enum AllowedSlots {
+    /// Only authorities that have a valid primary slot claim are allowed to author blocks.
+    #[codec(index = 0)]
+    PrimarySlots,
+    /// Primary slot claims and "plain" secondary slot claims are both allowed.
+    #[codec(index = 1)]
+    PrimaryAndSecondaryPlainSlots,
+    /// Primary slot claims and VRF-based secondary slot claims are both allowed.
+    #[codec(index = 2)]
+    PrimaryAndSecondaryVRFSlots,
+}
+
+impl AllowedSlots {
+    /// Returns `true` if secondary slot claims of any kind are allowed.
+    pub fn is_secondary_allowed(&self) -> bool {
+        !matches!(self, AllowedSlots::PrimarySlots)
+    }
+
+    /// Returns `true` if "plain" (non-VRF) secondary slot claims specifically are allowed.
+    pub fn is_secondary_plain_allowed(&self) -> bool {
+        matches!(self, AllowedSlots::PrimaryAndSecondaryPlainSlots)
+    }
+
+    /// Returns `true` if secondary VRF slot claims specifically are allowed.
+    pub fn is_secondary_vrf_allowed(&self) -> bool {
+        matches!(self, AllowedSlots::PrimaryAndSecondaryVRFSlots)
+    }
+}
+
+impl BabeGenesisConfiguration {
+    /// Retrieves the BABE configuration from the given virtual machine prototype.
+    ///
+    /// The `BabeApi_configuration` runtime entry point is not called: this function always
+    /// returns a [`FromVmPrototypeError::Runtime`] error.
+    pub fn from_virtual_machine_prototype(
+        vm: executor::host::HostVmPrototype,
+    ) -> Result<Self, FromVmPrototypeError> {
+        super::runtime::babe_configuration(vm).map_err(FromVmPrototypeError::Runtime)
+    }
+}
+
+/// Error potentially returned by [`BabeGenesisConfiguration::from_virtual_machine_prototype`].
+#[derive(Debug, derive_more::Display)]
+pub enum FromVmPrototypeError {
+    /// Error while executing the runtime entry point.
+    Runtime(super::runtime::BabeConfigurationError),
+}
@@ -0,0 +1,111 @@
+//! BABE fork-choice: deciding which of two chains is the "best" one.
+//!
+//! See the [module-level documentation](super#chain-selection) for the rule being implemented
+//! here.
+
+use core::cmp::Ordering;
+
+use super::definitions::PreDigest;
+
+/// A measure of how good a chain's tip is, for the purposes of BABE fork-choice.
+///
+/// Two [`ChainScore`]s are only meaningfully comparable if they were built starting from the
+/// same common ancestor; see [`ChainScore::child`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainScore {
+    /// Slot number of the block at the tip of the chain.
+    pub slot_number: u64,
+
+    /// Number of primary slot claims made along the chain since the ancestor the score was
+    /// first built from.
+    pub primary_slot_claims: u64,
+}
+
+impl ChainScore {
+    /// The score of a chain made of a single block, whose pre-runtime digest is `pre_digest`.
+    pub fn for_block(pre_digest: &PreDigest) -> ChainScore {
+        ChainScore {
+            slot_number: pre_digest.slot_number(),
+            primary_slot_claims: u64::from(matches!(pre_digest, PreDigest::Primary(_))),
+        }
+    }
+
+    /// The score of a chain obtained by appending a block, whose pre-runtime digest is
+    /// `pre_digest`, on top of a chain whose score is `self`.
+    pub fn child(&self, pre_digest: &PreDigest) -> ChainScore {
+        ChainScore {
+            slot_number: pre_digest.slot_number(),
+            primary_slot_claims: self.primary_slot_claims
+                + u64::from(matches!(pre_digest, PreDigest::Primary(_))),
+        }
+    }
+}
+
+/// Compares two chain tips according to the BABE fork-choice rule described in the
+/// [module-level documentation](super#chain-selection): the chain with the highest slot number
+/// wins; if slot numbers are equal, the chain with the most primary slot claims wins.
+///
+/// Returns [`Ordering::Greater`] if `a` is the best chain, [`Ordering::Less`] if `b` is, and
+/// [`Ordering::Equal`] if this rule alone cannot decide between the two (the caller should then
+/// apply its own tie-breaker, such as preferring the chain built upon by a later block).
+pub fn compare_chains(a: &ChainScore, b: &ChainScore) -> Ordering {
+    a.slot_number
+        .cmp(&b.slot_number)
+        .then_with(|| a.primary_slot_claims.cmp(&b.primary_slot_claims))
+}
+
+/// Returns whichever of `a` or `b` is the best chain tip, according to [`compare_chains`]. In
+/// case of a tie, returns `a`.
+pub fn best_block<'a>(a: &'a ChainScore, b: &'a ChainScore) -> &'a ChainScore {
+    match compare_chains(a, b) {
+        Ordering::Less => b,
+        Ordering::Equal | Ordering::Greater => a,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::babe::definitions::{PrimaryPreDigest, SecondaryPlainPreDigest};
+
+    fn primary(slot_number: u64) -> PreDigest {
+        PreDigest::Primary(PrimaryPreDigest {
+            authority_index: 0,
+            slot_number,
+            vrf_output: [0; 32],
+            vrf_proof: [0; 64],
+        })
+    }
+
+    fn secondary_plain(slot_number: u64) -> PreDigest {
+        PreDigest::SecondaryPlain(SecondaryPlainPreDigest {
+            authority_index: 0,
+            slot_number,
+        })
+    }
+
+    #[test]
+    fn higher_slot_number_wins() {
+        let a = ChainScore::for_block(&primary(5));
+        let b = ChainScore::for_block(&primary(6));
+        assert_eq!(compare_chains(&a, &b), Ordering::Less);
+        assert_eq!(best_block(&a, &b), &b);
+    }
+
+    #[test]
+    fn equal_slot_prefers_more_primary_claims() {
+        let a = ChainScore::for_block(&secondary_plain(5)).child(&primary(6));
+        let b = ChainScore::for_block(&primary(5)).child(&primary(6));
+        assert_eq!(a.slot_number, b.slot_number);
+        assert_eq!(compare_chains(&a, &b), Ordering::Less);
+        assert_eq!(best_block(&a, &b), &b);
+    }
+
+    #[test]
+    fn exact_tie_prefers_a() {
+        let a = ChainScore::for_block(&primary(5));
+        let b = ChainScore::for_block(&primary(5));
+        assert_eq!(compare_chains(&a, &b), Ordering::Equal);
+        assert_eq!(best_block(&a, &b), &a);
+    }
+}
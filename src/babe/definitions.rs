@@ -0,0 +1,135 @@
+//! Decoding of the BABE-specific digest items found in block headers.
+//!
+//! These types mirror the SCALE-encoded structures produced by the `pallet_babe` runtime module.
+
+use parity_scale_codec::{Decode, Encode};
+
+/// Content of a BABE pre-runtime digest, as found in [`crate::block::DigestItem::PreRuntime`].
+///
+/// This is the claim made by the author of a block that it was authorized to produce a block
+/// during a specific slot. BABE supports three kinds of slot claims; see the
+/// [module-level documentation](super) for an explanation of primary versus secondary slots.
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum PreDigest {
+    /// Claim of a primary slot, backed by a VRF proof that must fall below a threshold.
+    #[codec(index = 1)]
+    Primary(PrimaryPreDigest),
+
+    /// Claim of a secondary slot, without any VRF. The author must be the deterministic slot
+    /// leader for this slot.
+    #[codec(index = 2)]
+    SecondaryPlain(SecondaryPlainPreDigest),
+
+    /// Claim of a secondary slot, backed by a VRF proof. Unlike [`PreDigest::Primary`], the VRF
+    /// output isn't compared against any threshold.
+    #[codec(index = 3)]
+    SecondaryVRF(SecondaryVRFPreDigest),
+}
+
+impl PreDigest {
+    /// Index, within the list of authorities of the epoch, of the authority that claims to have
+    /// authored this block.
+    pub fn authority_index(&self) -> u32 {
+        match self {
+            PreDigest::Primary(digest) => digest.authority_index,
+            PreDigest::SecondaryPlain(digest) => digest.authority_index,
+            PreDigest::SecondaryVRF(digest) => digest.authority_index,
+        }
+    }
+
+    /// Slot number during which this block was authored.
+    pub fn slot_number(&self) -> u64 {
+        match self {
+            PreDigest::Primary(digest) => digest.slot_number,
+            PreDigest::SecondaryPlain(digest) => digest.slot_number,
+            PreDigest::SecondaryVRF(digest) => digest.slot_number,
+        }
+    }
+}
+
+/// See [`PreDigest::Primary`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct PrimaryPreDigest {
+    /// Index, within the list of authorities of the epoch, of the authority that claims to have
+    /// authored this block.
+    pub authority_index: u32,
+
+    /// Slot number during which this block was authored.
+    pub slot_number: u64,
+
+    /// VRF output embedded in the block.
+    pub vrf_output: [u8; 32],
+
+    /// VRF proof embedded in the block.
+    pub vrf_proof: [u8; 64],
+}
+
+/// See [`PreDigest::SecondaryPlain`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct SecondaryPlainPreDigest {
+    /// Index, within the list of authorities of the epoch, of the authority that claims to have
+    /// authored this block.
+    pub authority_index: u32,
+
+    /// Slot number during which this block was authored.
+    pub slot_number: u64,
+}
+
+/// See [`PreDigest::SecondaryVRF`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct SecondaryVRFPreDigest {
+    /// Index, within the list of authorities of the epoch, of the authority that claims to have
+    /// authored this block.
+    pub authority_index: u32,
+
+    /// Slot number during which this block was authored.
+    pub slot_number: u64,
+
+    /// VRF output embedded in the block.
+    pub vrf_output: [u8; 32],
+
+    /// VRF proof embedded in the block.
+    pub vrf_proof: [u8; 64],
+}
+
+/// Consensus digest log specific to BABE, as found in [`crate::block::DigestItem::Consensus`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum ConsensusLog {
+    /// Announces the parameters of the epoch that starts two epochs after the one of the block
+    /// containing this log. Must be present in the first block of every epoch.
+    #[codec(index = 1)]
+    NextEpochData(NextEpochDescriptor),
+
+    /// Disable the authority with the given index.
+    #[codec(index = 2)]
+    OnDisabled(u32),
+
+    /// Similar to [`ConsensusLog::NextEpochData`], but only generated when the BABE configuration
+    /// (the `c` constant and/or the allowed slots) changes.
+    #[codec(index = 3)]
+    NextConfigData(NextConfigDescriptor),
+}
+
+/// See [`ConsensusLog::NextEpochData`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct NextEpochDescriptor {
+    /// Authorities allowed to produce blocks during the announced epoch, alongside with their
+    /// weight.
+    pub authorities: Vec<(crate::sign::sr25519::PublicKey, u64)>,
+
+    /// Randomness value for the announced epoch.
+    pub randomness: [u8; 32],
+}
+
+/// See [`ConsensusLog::NextConfigData`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum NextConfigDescriptor {
+    /// Only variant for now.
+    #[codec(index = 1)]
+    V1 {
+        /// See [`crate::babe::chain_config::BabeGenesisConfiguration::c`].
+        c: (u64, u64),
+        /// See [`crate::babe::chain_config::BabeGenesisConfiguration::allowed_slots`].
+        allowed_slots: super::chain_config::AllowedSlots,
+    },
+}
@@ -0,0 +1,145 @@
+//! Tracking of BABE epoch information across epoch transitions.
+//!
+//! BABE announces the parameters of epoch `N + 2` (the authorities, their weights, the
+//! randomness, and optionally an updated `c`/`allowed_slots`) through a consensus digest log
+//! found in a block of epoch `N`. See [`EpochChange`] and [`EpochInformation::apply_epoch_change`].
+
+use super::chain_config::{AllowedSlots, BabeGenesisConfiguration};
+
+/// Epoch-transition information extracted from the consensus digest logs of a verified header.
+///
+/// See [`EpochInformation::apply_epoch_change`] to turn this, alongside the currently-known
+/// epoch information, into the information of the epoch two epochs ahead.
+#[derive(Debug, Clone)]
+pub struct EpochChange {
+    /// Authorities, alongside with their weight, allowed to author blocks during the new epoch.
+    pub authorities: Vec<(crate::sign::sr25519::PublicKey, u64)>,
+
+    /// Randomness value for the new epoch.
+    pub randomness: [u8; 32],
+
+    /// Updated BABE configuration, if the epoch change came with a configuration change.
+    ///
+    /// If `None`, the new epoch keeps the same `c` and `allowed_slots` as the epoch during
+    /// which the change was announced.
+    pub config: Option<EpochConfigChange>,
+}
+
+/// See [`EpochChange::config`].
+#[derive(Debug, Clone)]
+pub struct EpochConfigChange {
+    /// New value of [`EpochInformation::c`].
+    pub c: (u64, u64),
+    /// New value of [`EpochInformation::allowed_slots`].
+    pub allowed_slots: AllowedSlots,
+}
+
+/// State of a BABE epoch, as known by a light client tracking the chain.
+#[derive(Debug, Clone)]
+pub struct EpochInformation {
+    /// Index of this epoch. The genesis block belongs to epoch 0.
+    pub epoch_index: u64,
+
+    /// Authorities, alongside with their weight, allowed to author blocks during this epoch.
+    pub authorities: Vec<(crate::sign::sr25519::PublicKey, u64)>,
+
+    /// Randomness value for this epoch.
+    pub randomness: [u8; 32],
+
+    /// Value of the constant `c` used to calculate the slot-claiming threshold of primary slots.
+    pub c: (u64, u64),
+
+    /// Types of slots that authorities are allowed to claim during this epoch.
+    pub allowed_slots: AllowedSlots,
+}
+
+impl EpochInformation {
+    /// Builds the [`EpochInformation`] of `epoch_index` (which must be `0` or `1`, since BABE
+    /// genesis configuration applies unchanged to the first two epochs only) from the chain's
+    /// [`BabeGenesisConfiguration`].
+    pub fn from_genesis_configuration(
+        genesis_configuration: &BabeGenesisConfiguration,
+        epoch_index: u64,
+    ) -> EpochInformation {
+        debug_assert!(epoch_index == 0 || epoch_index == 1);
+        EpochInformation {
+            epoch_index,
+            authorities: genesis_configuration.genesis_authorities.clone(),
+            randomness: genesis_configuration.randomness,
+            c: genesis_configuration.c,
+            allowed_slots: genesis_configuration.allowed_slots,
+        }
+    }
+
+    /// Combines this epoch's information with an [`EpochChange`] observed in one of its blocks,
+    /// producing the [`EpochInformation`] of the epoch two epochs ahead (`self.epoch_index + 2`).
+    pub fn apply_epoch_change(&self, change: &EpochChange) -> EpochInformation {
+        let (c, allowed_slots) = match &change.config {
+            Some(config) => (config.c, config.allowed_slots),
+            None => (self.c, self.allowed_slots),
+        };
+
+        EpochInformation {
+            epoch_index: self.epoch_index + 2,
+            authorities: change.authorities.clone(),
+            randomness: change.randomness,
+            c,
+            allowed_slots,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authority(byte: u8) -> crate::sign::sr25519::PublicKey {
+        crate::sign::sr25519::PublicKey::try_from(&[byte; 32][..]).unwrap()
+    }
+
+    #[test]
+    fn epoch_change_without_config_keeps_previous_c_and_allowed_slots() {
+        let current = EpochInformation {
+            epoch_index: 3,
+            authorities: vec![(authority(1), 1)],
+            randomness: [0; 32],
+            c: (1, 4),
+            allowed_slots: AllowedSlots::PrimaryAndSecondaryPlainSlots,
+        };
+        let change = EpochChange {
+            authorities: vec![(authority(2), 1)],
+            randomness: [9; 32],
+            config: None,
+        };
+
+        let next = current.apply_epoch_change(&change);
+        assert_eq!(next.epoch_index, 5);
+        assert!(next.authorities == change.authorities);
+        assert_eq!(next.randomness, [9; 32]);
+        assert_eq!(next.c, (1, 4));
+        assert_eq!(next.allowed_slots, AllowedSlots::PrimaryAndSecondaryPlainSlots);
+    }
+
+    #[test]
+    fn epoch_change_with_config_overrides_c_and_allowed_slots() {
+        let current = EpochInformation {
+            epoch_index: 3,
+            authorities: vec![(authority(1), 1)],
+            randomness: [0; 32],
+            c: (1, 4),
+            allowed_slots: AllowedSlots::PrimarySlots,
+        };
+        let change = EpochChange {
+            authorities: vec![(authority(2), 1)],
+            randomness: [9; 32],
+            config: Some(EpochConfigChange {
+                c: (1, 2),
+                allowed_slots: AllowedSlots::PrimaryAndSecondaryVRFSlots,
+            }),
+        };
+
+        let next = current.apply_epoch_change(&change);
+        assert_eq!(next.c, (1, 2));
+        assert_eq!(next.allowed_slots, AllowedSlots::PrimaryAndSecondaryVRFSlots);
+    }
+}
@@ -0,0 +1,119 @@
+//! Detection of BABE equivocations: the same authority claiming the same slot in two different
+//! blocks.
+//!
+//! The two headers responsible for an [`Equivocation`] together form a verifiable proof that can
+//! be forwarded on-chain, similarly to how Substrate's slot-claiming code implements
+//! `check_equivocation`.
+
+use alloc::collections::btree_map::{BTreeMap, Entry};
+
+/// Tracks, across the headers passed to [`super::verify_header`], which authority claimed which
+/// slot, in order to detect equivocations.
+#[derive(Debug, Clone, Default)]
+pub struct SlotClaimTracker {
+    /// For each `(slot_number, authority_public_key)` that has been observed, the hash of the
+    /// first header that made that claim.
+    claims: BTreeMap<(u64, crate::sign::sr25519::PublicKey), [u8; 32]>,
+}
+
+impl SlotClaimTracker {
+    /// Creates a new, empty [`SlotClaimTracker`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a verified slot claim made by `authority_public_key` for `slot_number`, in the
+    /// header whose pre-seal hash is `header_hash`.
+    ///
+    /// If the same authority already claimed this slot in a header with a different hash, an
+    /// [`Equivocation`] is returned. Claims made multiple times by the same header (i.e. with an
+    /// identical `header_hash`) do not count as an equivocation.
+    pub fn observe(
+        &mut self,
+        slot_number: u64,
+        authority_index: u32,
+        authority_public_key: crate::sign::sr25519::PublicKey,
+        header_hash: [u8; 32],
+    ) -> Option<Equivocation> {
+        match self.claims.entry((slot_number, authority_public_key)) {
+            Entry::Occupied(entry) => {
+                let first_header_hash = *entry.get();
+                if first_header_hash == header_hash {
+                    None
+                } else {
+                    Some(Equivocation {
+                        slot: slot_number,
+                        authority_index,
+                        first_header_hash,
+                        second_header_hash: header_hash,
+                    })
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(header_hash);
+                None
+            }
+        }
+    }
+}
+
+/// Proof that an authority equivocated: it claimed the same slot in two distinct headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equivocation {
+    /// Slot that was claimed twice.
+    pub slot: u64,
+
+    /// Index, within the epoch's authority list, of the authority that equivocated.
+    pub authority_index: u32,
+
+    /// Pre-seal hash of the first header observed making the claim.
+    pub first_header_hash: [u8; 32],
+
+    /// Pre-seal hash of the second header observed making the claim.
+    pub second_header_hash: [u8; 32],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authority(byte: u8) -> crate::sign::sr25519::PublicKey {
+        crate::sign::sr25519::PublicKey::try_from(&[byte; 32][..]).unwrap()
+    }
+
+    #[test]
+    fn first_claim_is_not_an_equivocation() {
+        let mut tracker = SlotClaimTracker::new();
+        assert!(tracker.observe(10, 0, authority(1), [1; 32]).is_none());
+    }
+
+    #[test]
+    fn repeating_the_same_header_is_not_an_equivocation() {
+        let mut tracker = SlotClaimTracker::new();
+        assert!(tracker.observe(10, 0, authority(1), [1; 32]).is_none());
+        assert!(tracker.observe(10, 0, authority(1), [1; 32]).is_none());
+    }
+
+    #[test]
+    fn same_slot_different_header_is_an_equivocation() {
+        let mut tracker = SlotClaimTracker::new();
+        assert!(tracker.observe(10, 0, authority(1), [1; 32]).is_none());
+        let equivocation = tracker.observe(10, 0, authority(1), [2; 32]).unwrap();
+        assert_eq!(
+            equivocation,
+            Equivocation {
+                slot: 10,
+                authority_index: 0,
+                first_header_hash: [1; 32],
+                second_header_hash: [2; 32],
+            }
+        );
+    }
+
+    #[test]
+    fn different_authorities_claiming_the_same_slot_do_not_conflict() {
+        let mut tracker = SlotClaimTracker::new();
+        assert!(tracker.observe(10, 0, authority(1), [1; 32]).is_none());
+        assert!(tracker.observe(10, 1, authority(2), [2; 32]).is_none());
+    }
+}
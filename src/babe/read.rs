@@ -0,0 +1,126 @@
+//! Extraction of the BABE-related information contained in a block header, without performing
+//! any cryptographic verification.
+//!
+//! This is split out of [`super::verify_header`] so that a header can cheaply be examined (for
+//! example to read its slot number, or to detect an epoch transition) without paying the cost of
+//! signature and VRF verification, and without requiring the epoch's authority list to be known
+//! in advance.
+
+use parity_scale_codec::DecodeAll as _;
+
+use super::definitions;
+
+/// Information extracted from a BABE-produced block header.
+#[derive(Debug, Clone)]
+pub struct HeaderInfo {
+    /// Seal digest item, signing [`HeaderInfo::pre_seal_hash`]. This is the last digest log of
+    /// the header.
+    pub seal_signature: Vec<u8>,
+
+    /// Slot claim made by the author of the block.
+    pub pre_digest: definitions::PreDigest,
+
+    /// List of BABE consensus digest logs (epoch changes and/or configuration changes) found in
+    /// the header, in the order in which they appear.
+    pub consensus_logs: Vec<definitions::ConsensusLog>,
+
+    /// Blake2-256 hash of the SCALE-encoded header, with the seal digest item removed. This is
+    /// the value that [`HeaderInfo::seal_signature`] is a signature of.
+    pub pre_seal_hash: [u8; 32],
+}
+
+/// Error potentially returned by [`read_header`].
+#[derive(Debug, Clone, derive_more::Display)]
+pub enum ReadError {
+    /// Header passed is of the wrong format.
+    InvalidHeader,
+    /// The seal (containing the signature of the authority) is missing from the header.
+    MissingSeal,
+    /// No pre-runtime digest in the block header.
+    MissingPreRuntimeDigest,
+    /// There are multiple pre-runtime digests in the block header.
+    MultiplePreRuntimeDigests,
+    /// Failed to decode pre-runtime digest.
+    PreRuntimeDigestDecodeError(parity_scale_codec::Error),
+    /// Failed to decode a consensus digest.
+    ConsensusDigestDecodeError(parity_scale_codec::Error),
+}
+
+/// Extracts the BABE-related information (slot claim, consensus logs, seal) from a
+/// SCALE-encoded header, without verifying any of it.
+///
+/// This only checks that the header is well-formed with respect to the BABE consensus engine
+/// (the seal and pre-runtime digest are present and well-formed); it performs no cryptographic
+/// verification whatsoever. Use [`super::verify_header`] to additionally check the legitimacy of
+/// the slot claim.
+pub fn read_header(scale_encoded_header: &[u8]) -> Result<HeaderInfo, ReadError> {
+    let header =
+        crate::block::Header::decode_all(scale_encoded_header).map_err(|_| ReadError::InvalidHeader)?;
+
+    // Part of the rules is that the last digest log of the header must always be the seal,
+    // containing a signature of the rest of the header and made by the author of the block.
+    let seal_signature: Vec<u8> = header
+        .digest
+        .logs
+        .last()
+        .and_then(|l| match l {
+            crate::block::DigestItem::Seal(engine, signature) if engine == b"BABE" => {
+                Some(signature.clone())
+            }
+            _ => None,
+        })
+        .ok_or(ReadError::MissingSeal)?;
+
+    // Additionally, one of the digest logs of the header must be a BABE pre-runtime digest whose
+    // content contains the slot claim made by the author.
+    let pre_digest: definitions::PreDigest = {
+        let mut pre_runtime_digests = header.digest.logs.iter().filter_map(|l| match l {
+            crate::block::DigestItem::PreRuntime(engine, data) if engine == b"BABE" => Some(data),
+            _ => None,
+        });
+        let pre_runtime = pre_runtime_digests
+            .next()
+            .ok_or(ReadError::MissingPreRuntimeDigest)?;
+        if pre_runtime_digests.next().is_some() {
+            return Err(ReadError::MultiplePreRuntimeDigests);
+        }
+        definitions::PreDigest::decode_all(pre_runtime)
+            .map_err(ReadError::PreRuntimeDigestDecodeError)?
+    };
+
+    // Finally, the header can contain consensus digest logs, indicating an epoch transition or
+    // a configuration change.
+    let consensus_logs: Vec<definitions::ConsensusLog> = {
+        let list = header.digest.logs.iter().filter_map(|l| match l {
+            crate::block::DigestItem::Consensus(engine, data) if engine == b"BABE" => Some(data),
+            _ => None,
+        });
+
+        let mut consensus_logs = Vec::with_capacity(header.digest.logs.len());
+        for digest in list {
+            let decoded = definitions::ConsensusLog::decode_all(digest)
+                .map_err(ReadError::ConsensusDigestDecodeError)?;
+            consensus_logs.push(decoded)
+        }
+        consensus_logs
+    };
+
+    // The signature of the block header applies to the header from where the signature isn't
+    // present.
+    let pre_seal_hash = {
+        let mut unsealed_header = header.clone();
+        let _popped = unsealed_header.digest.logs.pop();
+        debug_assert!(matches!(
+            _popped,
+            Some(crate::block::DigestItem::Seal(_, _))
+        ));
+        unsealed_header.block_hash()
+    };
+
+    Ok(HeaderInfo {
+        seal_signature,
+        pre_digest,
+        consensus_logs,
+        pre_seal_hash,
+    })
+}
@@ -0,0 +1,24 @@
+//! Helpers to call into the runtime in order to obtain BABE-related information.
+
+use crate::executor;
+
+use super::chain_config::BabeGenesisConfiguration;
+
+/// Obtains a [`BabeGenesisConfiguration`] from the given virtual machine prototype.
+///
+/// The `BabeApi_configuration` entry point is not called: this function always returns
+/// [`BabeConfigurationError::EntryPointMissing`], whatever the virtual machine provides.
+pub(super) fn babe_configuration(
+    _vm: executor::host::HostVmPrototype,
+) -> Result<BabeGenesisConfiguration, BabeConfigurationError> {
+    // TODO: actually call into the virtual machine; this requires wiring up the
+    // `BabeApi_configuration` entry point and SCALE-decoding its return value
+    Err(BabeConfigurationError::EntryPointMissing)
+}
+
+/// Error potentially returned by [`babe_configuration`].
+#[derive(Debug, derive_more::Display)]
+pub enum BabeConfigurationError {
+    /// Virtual machine doesn't provide the `BabeApi_configuration` entry point.
+    EntryPointMissing,
+}